@@ -1,13 +1,25 @@
 use image::RgbaImage;
 use std::mem;
 use windows::Win32::{
-    Foundation::HWND,
+    Foundation::{HWND, POINT, RECT},
+    Graphics::Direct3D::D3D_DRIVER_TYPE_HARDWARE,
+    Graphics::Direct3D11::{
+        D3D11CreateDevice, ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D,
+        D3D11_CPU_ACCESS_READ, D3D11_MAPPED_SUBRESOURCE, D3D11_MAP_READ, D3D11_SDK_VERSION,
+        D3D11_TEXTURE2D_DESC, D3D11_USAGE_STAGING,
+    },
+    Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS},
+    Graphics::Dxgi::{
+        IDXGIDevice, IDXGIOutput1, IDXGIOutputDuplication, IDXGIResource, DXGI_ERROR_ACCESS_LOST,
+        DXGI_OUTDUPL_FRAME_INFO,
+    },
     Graphics::Gdi::{
-        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, GetDIBits, SelectObject, BITMAPINFO,
-        BITMAPINFOHEADER, DIB_RGB_COLORS, SRCCOPY,
+        BitBlt, CreateCompatibleDC, CreateDIBSection, GdiFlush, PrintWindow, SelectObject,
+        BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, PW_RENDERFULLCONTENT, SRCCOPY,
     },
     UI::WindowsAndMessaging::{
-        GetDesktopWindow, GetSystemMetrics, SetProcessDPIAware, SM_CXSCREEN, SM_CYSCREEN,
+        ClientToScreen, GetClientRect, GetDesktopWindow, GetSystemMetrics, SetProcessDPIAware,
+        SM_CXSCREEN, SM_CYSCREEN,
     },
 };
 
@@ -21,76 +33,163 @@ use super::{
     utils::get_os_major_version,
 };
 
-fn to_rgba_image(
-    box_hdc_mem: BoxHDC,
-    box_h_bitmap: BoxHBITMAP,
+// CreateDIBSection 直接把 DIB 的像素内存映射到进程地址空间，BitBlt/PrintWindow 画完之后
+// 就可以通过返回的指针读像素，省去 GetDIBits 对整张位图再做一次拷贝
+unsafe fn create_dib_section_bitmap(
+    hdc: windows::Win32::Graphics::Gdi::HDC,
     width: i32,
     height: i32,
-) -> XCapResult<RgbaImage> {
-    let buffer_size = width * height * 4;
-    let mut bitmap_info = BITMAPINFO {
+) -> XCapResult<(BoxHBITMAP, *mut u8)> {
+    let bitmap_info = BITMAPINFO {
         bmiHeader: BITMAPINFOHEADER {
             biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
             biWidth: width,
+            // 高度为负表示自顶向下的位图，和 GDI 截屏惯用的行序一致
             biHeight: -height,
             biPlanes: 1,
             biBitCount: 32,
-            biSizeImage: buffer_size as u32,
-            biCompression: 0,
+            biCompression: 0, // BI_RGB
             ..Default::default()
         },
         ..Default::default()
     };
 
-    let mut buffer = vec![0u8; buffer_size as usize];
+    let mut bits: *mut core::ffi::c_void = std::ptr::null_mut();
+    let h_bitmap = CreateDIBSection(hdc, &bitmap_info, DIB_RGB_COLORS, &mut bits, None, 0)?;
 
-    unsafe {
-        // 读取数据到 buffer 中
-        let is_success = GetDIBits(
-            *box_hdc_mem,
-            *box_h_bitmap,
-            0,
-            height as u32,
-            Some(buffer.as_mut_ptr().cast()),
-            &mut bitmap_info,
-            DIB_RGB_COLORS,
-        ) == 0;
+    if bits.is_null() {
+        return Err(XCapError::new("CreateDIBSection returned a null buffer"));
+    }
+
+    Ok((BoxHBITMAP::new(h_bitmap), bits.cast()))
+}
 
-        if is_success {
-            return Err(XCapError::new("Get RGBA data failed"));
+/// 截图输出使用的像素格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PixelFormat {
+    /// image 生态习惯的 R-G-B-A 排列，默认格式
+    #[default]
+    Rgba,
+    /// GDI/DXGI 原生的 B-G-R-A 排列，省去逐像素交换通道的开销，适合直接喂给编码器/GPU 纹理
+    Bgra,
+}
+
+/// 截图结果。`Bgra` 请求不会套进 [`RgbaImage`]（也就是 `ImageBuffer<Rgba<u8>, _>`），
+/// 因为那样类型会声称数据是 R-G-B-A，实际字节序却是 B-G-R-A，任何信任该类型的
+/// 调用方（包括 image 自己的编解码器）都会把红蓝通道读反
+#[derive(Debug, Clone)]
+pub enum CapturedImage {
+    Rgba(RgbaImage),
+    Bgra {
+        width: u32,
+        height: u32,
+        /// 原始 B-G-R-A 字节序的像素数据
+        buffer: Vec<u8>,
+    },
+}
+
+impl CapturedImage {
+    fn from_buffer(
+        width: i32,
+        height: i32,
+        buffer: Vec<u8>,
+        pixel_format: PixelFormat,
+    ) -> XCapResult<Self> {
+        match pixel_format {
+            PixelFormat::Rgba => RgbaImage::from_raw(width as u32, height as u32, buffer)
+                .map(CapturedImage::Rgba)
+                .ok_or_else(|| XCapError::new("RgbaImage::from_raw failed")),
+            PixelFormat::Bgra => Ok(CapturedImage::Bgra {
+                width: width as u32,
+                height: height as u32,
+                buffer,
+            }),
         }
-    };
+    }
+
+    // capture_monitor/capture_window 这类简单入口内部写死了 PixelFormat::Rgba，
+    // 按上面 from_buffer 的映射关系，Bgra 分支正常不会被走到，但仍然返回 XCapResult
+    // 而不是 panic，避免以后有人改出一条会把 Bgra 请求喂进来的路径时直接把进程搞挂
+    fn into_rgba(self) -> XCapResult<RgbaImage> {
+        match self {
+            CapturedImage::Rgba(image) => Ok(image),
+            CapturedImage::Bgra { .. } => Err(XCapError::new(
+                "expected CapturedImage::Rgba but got CapturedImage::Bgra",
+            )),
+        }
+    }
+}
+
+unsafe fn dib_section_to_image(
+    bits: *const u8,
+    width: i32,
+    height: i32,
+    pixel_format: PixelFormat,
+) -> XCapResult<CapturedImage> {
+    let buffer_size = (width * height * 4) as usize;
+    let mut buffer = vec![0u8; buffer_size];
+    std::ptr::copy_nonoverlapping(bits, buffer.as_mut_ptr(), buffer_size);
 
-    for src in buffer.chunks_exact_mut(4) {
-        src.swap(0, 2);
+    for pixel in buffer.chunks_exact_mut(4) {
+        if pixel_format == PixelFormat::Rgba {
+            pixel.swap(0, 2);
+        }
         // fix https://github.com/nashaofu/xcap/issues/92#issuecomment-1910014951
-        if src[3] == 0 && get_os_major_version() < 8 {
-            src[3] = 255;
+        if pixel[3] == 0 && get_os_major_version() < 8 {
+            pixel[3] = 255;
         }
     }
 
-    RgbaImage::from_raw(width as u32, height as u32, buffer)
-        .ok_or_else(|| XCapError::new("RgbaImage::from_raw failed"))
+    CapturedImage::from_buffer(width, height, buffer, pixel_format)
 }
 
 #[allow(unused)]
 pub fn capture_monitor(x: i32, y: i32, width: i32, height: i32) -> XCapResult<RgbaImage> {
+    capture_monitor_with_format(x, y, width, height, PixelFormat::Rgba)
+        .and_then(CapturedImage::into_rgba)
+}
+
+#[allow(unused)]
+pub fn capture_monitor_with_format(
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    pixel_format: PixelFormat,
+) -> XCapResult<CapturedImage> {
+    // SetProcessDPIAware 必须在算坐标/匹配显示器之前跑一次，不管最终走的是 DXGI 还是
+    // GDI 路径，否则 find_dxgi_output1 按物理像素匹配 DesktopCoordinates 时会用到
+    // 尚未做 DPI 感知的坐标，从而匹配错显示器或直接报错
     unsafe {
         SetProcessDPIAware();
+    }
+
+    // Desktop Duplication 只在 Windows 8 及以上可用，部分虚拟机/远程桌面环境下
+    // 设备或 duplication 对象也可能创建失败，这些情况都回退到旧的 GDI BitBlt 方案
+    if get_os_major_version() >= 8 {
+        if let Ok(captured_image) = capture_monitor_dxgi(x, y, width, height, pixel_format) {
+            return Ok(captured_image);
+        }
+    }
+
+    capture_monitor_gdi(x, y, width, height, pixel_format)
+}
+
+fn capture_monitor_gdi(
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    pixel_format: PixelFormat,
+) -> XCapResult<CapturedImage> {
+    unsafe {
         let hwnd = GetDesktopWindow();
         let box_hdc_desktop_window = BoxHDC::from(hwnd);
 
         // 内存中的HDC，使用 DeleteDC 函数释放
         // https://learn.microsoft.com/zh-cn/windows/win32/api/wingdi/nf-wingdi-createcompatibledc
-        let box_hdc_mem = BoxHDC::new(CreateCompatibleDC(*box_hdc_desktop_window), None);
-        let box_h_bitmap = BoxHBITMAP::new(CreateCompatibleBitmap(
-            *box_hdc_desktop_window,
-            width,
-            height,
-        ));
-
-        // 使用SelectObject函数将这个位图选择到DC中
-        SelectObject(*box_hdc_mem, *box_h_bitmap);
+        let (box_hdc_mem, _box_h_bitmap, bits) =
+            create_mem_dc_and_bitmap(*box_hdc_desktop_window, width, height)?;
 
         // 拷贝原始图像到内存
         // 这里不需要缩放图片，所以直接使用BitBlt
@@ -107,64 +206,633 @@ pub fn capture_monitor(x: i32, y: i32, width: i32, height: i32) -> XCapResult<Rg
             SRCCOPY,
         )?;
 
-        to_rgba_image(box_hdc_mem, box_h_bitmap, width, height)
+        // GDI 会对 DC 上的绘制调用做批处理，DIB section 的内存不保证在 BitBlt 返回时
+        // 就已经写完；GetDIBits 本身会隐式 flush，但这里绕过了它直接读指针，所以要自己
+        // 调 GdiFlush 强制落盘，否则可能读到旧帧或画了一半的数据
+        GdiFlush();
+
+        dib_section_to_image(bits, width, height, pixel_format)
     }
 }
 
-#[allow(unused)]
-pub fn capture_window(hwnd: HWND, scale_factor: f32) -> XCapResult<RgbaImage> {
+// 在显示器对应的 IDXGIOutput1 上建立一次性的 D3D11 设备 + Desktop Duplication，
+// 抓一帧后立刻释放，性能不如 CaptureSession 那样复用资源，但胜在调用方式和
+// capture_monitor_gdi 一致，调用者无需关心底层用的是哪条路径
+fn capture_monitor_dxgi(
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    pixel_format: PixelFormat,
+) -> XCapResult<CapturedImage> {
     unsafe {
-        SetProcessDPIAware();
-        let dw_hwnd = GetDesktopWindow();
-        let box_hdc_desktop_window: BoxHDC = BoxHDC::from(dw_hwnd);
-        let box_hdc_window: BoxHDC = BoxHDC::from(hwnd);
-        let rect = get_window_rect(hwnd)?;
-        let mut width = rect.right - rect.left;
-        let mut height = rect.bottom - rect.top;
+        let (device, output1, mut duplication) = create_dxgi_duplication(x, y, width, height)?;
 
-        if width == 0 {
-            width = GetSystemMetrics(SM_CXSCREEN);
+        let buffer = match capture_dxgi_frame(&device, &duplication, width, height) {
+            Ok(buffer) => buffer,
+            Err(err) if err.code() == DXGI_ERROR_ACCESS_LOST => {
+                // 桌面切换、锁屏、UAC 弹窗等都会让 duplication 对象失效，重建后再试一次
+                duplication = output1.DuplicateOutput(&device)?;
+                capture_dxgi_frame(&device, &duplication, width, height)?
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        dxgi_buffer_to_image(buffer, width, height, pixel_format)
+    }
+}
+
+// 建立一次 D3D11 设备并定位到匹配 (x, y, width, height) 这块区域的 IDXGIOutput1，
+// 返回的 IDXGIOutputDuplication 可以反复喂给 capture_dxgi_frame 连续抓帧，
+// 不需要每帧都重新创建设备——CaptureSession 靠这个来复用 DXGI 资源
+unsafe fn create_dxgi_duplication(
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+) -> XCapResult<(ID3D11Device, IDXGIOutput1, IDXGIOutputDuplication)> {
+    let mut device: Option<ID3D11Device> = None;
+    D3D11CreateDevice(
+        None,
+        D3D_DRIVER_TYPE_HARDWARE,
+        None,
+        Default::default(),
+        None,
+        D3D11_SDK_VERSION,
+        Some(&mut device),
+        None,
+        None,
+    )?;
+    let device = device.ok_or_else(|| XCapError::new("D3D11CreateDevice returned no device"))?;
+
+    let output1 = find_dxgi_output1(&device, x, y, width, height)?;
+    let duplication = output1.DuplicateOutput(&device)?;
+
+    Ok((device, output1, duplication))
+}
+
+unsafe fn find_dxgi_output1(
+    device: &ID3D11Device,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+) -> XCapResult<IDXGIOutput1> {
+    let dxgi_device: IDXGIDevice = device.cast()?;
+    let dxgi_adapter = dxgi_device.GetAdapter()?;
+
+    let mut output_index = 0;
+    loop {
+        let output = match dxgi_adapter.EnumOutputs(output_index) {
+            Ok(output) => output,
+            Err(_) => break,
+        };
+        output_index += 1;
+
+        let desc = output.GetDesc()?;
+        let desktop_coordinates = desc.DesktopCoordinates;
+
+        if desktop_coordinates.left == x
+            && desktop_coordinates.top == y
+            && desktop_coordinates.right - desktop_coordinates.left == width
+            && desktop_coordinates.bottom - desktop_coordinates.top == height
+        {
+            return Ok(output.cast()?);
         }
-        if height == 0 {
-            height = GetSystemMetrics(SM_CYSCREEN);
+    }
+
+    Err(XCapError::new(
+        "No IDXGIOutput1 matches the requested monitor rect",
+    ))
+}
+
+unsafe fn capture_dxgi_frame(
+    device: &ID3D11Device,
+    duplication: &IDXGIOutputDuplication,
+    width: i32,
+    height: i32,
+) -> windows::core::Result<Vec<u8>> {
+    let mut frame_info = DXGI_OUTDUPL_FRAME_INFO::default();
+    let mut resource: Option<IDXGIResource> = None;
+    duplication.AcquireNextFrame(500, &mut frame_info, &mut resource)?;
+
+    // AcquireNextFrame 成功之后，下一次 AcquireNextFrame 必须等这一帧被 ReleaseFrame
+    // 才会再成功；读取过程中任何一步通过 ? 提前返回都不能跳过释放，所以把读取结果先
+    // 存起来，确保不管成功与否都会走到 ReleaseFrame
+    let result = read_dxgi_resource(device, resource, width, height);
+    let _ = duplication.ReleaseFrame();
+
+    result
+}
+
+unsafe fn read_dxgi_resource(
+    device: &ID3D11Device,
+    resource: Option<IDXGIResource>,
+    width: i32,
+    height: i32,
+) -> windows::core::Result<Vec<u8>> {
+    let resource = resource.ok_or_else(|| windows::core::Error::from(DXGI_ERROR_ACCESS_LOST))?;
+    let acquired_texture: ID3D11Texture2D = resource.cast()?;
+
+    let mut texture_desc = D3D11_TEXTURE2D_DESC::default();
+    acquired_texture.GetDesc(&mut texture_desc);
+
+    let staging_desc = D3D11_TEXTURE2D_DESC {
+        Usage: D3D11_USAGE_STAGING,
+        BindFlags: 0,
+        CPUAccessFlags: D3D11_CPU_ACCESS_READ.0 as u32,
+        MiscFlags: 0,
+        ..texture_desc
+    };
+
+    let mut staging_texture: Option<ID3D11Texture2D> = None;
+    device.CreateTexture2D(&staging_desc, None, Some(&mut staging_texture))?;
+    let staging_texture =
+        staging_texture.ok_or_else(|| windows::core::Error::from(DXGI_ERROR_ACCESS_LOST))?;
+
+    let mut context: Option<ID3D11DeviceContext> = None;
+    device.GetImmediateContext(&mut context);
+    let context = context.ok_or_else(|| windows::core::Error::from(DXGI_ERROR_ACCESS_LOST))?;
+
+    context.CopyResource(&staging_texture, &acquired_texture);
+
+    let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+    context.Map(&staging_texture, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+
+    let row_bytes = (width * 4) as usize;
+    let mut buffer = vec![0u8; row_bytes * height as usize];
+    let src = mapped.pData as *const u8;
+
+    for y in 0..height as usize {
+        let src_row = src.add(y * mapped.RowPitch as usize);
+        let dst_row = buffer.as_mut_ptr().add(y * row_bytes);
+        std::ptr::copy_nonoverlapping(src_row, dst_row, row_bytes);
+    }
+
+    context.Unmap(&staging_texture, 0);
+
+    Ok(buffer)
+}
+
+fn dxgi_buffer_to_image(
+    mut buffer: Vec<u8>,
+    width: i32,
+    height: i32,
+    pixel_format: PixelFormat,
+) -> XCapResult<CapturedImage> {
+    if pixel_format == PixelFormat::Rgba {
+        for pixel in buffer.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
         }
+    }
 
-        let mut horizontal_scale = 1.0;
-        let mut vertical_scale = 1.0;
+    CapturedImage::from_buffer(width, height, buffer, pixel_format)
+}
 
-        width = (width as f32 * scale_factor) as i32;
-        height = (height as f32 * scale_factor) as i32;
+// PrintWindow 对部分使用 D3D/DWM 合成的窗口只会填充一块全透明或全黑的缓冲区，
+// 这里抽样几行数据做一次快速探测：抽到的像素 alpha 全为 0 时认为 PrintWindow 没能
+// 拿到真实内容。只检查 alpha 通道，alpha 不为 0 的全黑缓冲区不会被识别为空。
+// bits 直接指向 CreateDIBSection 的像素内存，按顶行在前的顺序排列
+unsafe fn is_print_window_buffer_empty(bits: *const u8, width: i32, height: i32) -> bool {
+    let row_bytes = (width * 4) as usize;
+    let sample_rows = [0, height / 4, height / 2, height * 3 / 4, height - 1];
 
-        // 内存中的HDC，使用 DeleteDC 函数释放
-        // https://learn.microsoft.com/zh-cn/windows/win32/api/wingdi/nf-wingdi-createcompatibledc
-        let box_hdc_mem = BoxHDC::new(CreateCompatibleDC(*box_hdc_desktop_window), None);
-        let box_h_bitmap = BoxHBITMAP::new(CreateCompatibleBitmap(
-            *box_hdc_desktop_window,
-            width,
-            height,
-        ));
+    for &y in sample_rows.iter() {
+        if y < 0 || y >= height {
+            continue;
+        }
+
+        let row = std::slice::from_raw_parts(bits.add(y as usize * row_bytes), row_bytes);
+        if row.chunks_exact(4).any(|pixel| pixel[3] != 0) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// 窗口截图覆盖的区域范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowCaptureArea {
+    /// GetWindowRect 返回的整窗口矩形，包含不可见的 DWM 阴影
+    #[default]
+    FullWindow,
+    /// DwmGetWindowAttribute(DWMWA_EXTENDED_FRAME_BOUNDS) 得到的真实可见边界，裁掉了阴影
+    ExtendedFrame,
+    /// GetClientRect + ClientToScreen 得到的客户区，不含标题栏和边框
+    ClientArea,
+}
+
+unsafe fn window_capture_rect(hwnd: HWND, capture_area: WindowCaptureArea) -> XCapResult<RECT> {
+    match capture_area {
+        WindowCaptureArea::FullWindow => get_window_rect(hwnd),
+        WindowCaptureArea::ExtendedFrame => {
+            let mut rect = RECT::default();
+            DwmGetWindowAttribute(
+                hwnd,
+                DWMWA_EXTENDED_FRAME_BOUNDS,
+                &mut rect as *mut RECT as *mut _,
+                mem::size_of::<RECT>() as u32,
+            )?;
+
+            Ok(rect)
+        }
+        WindowCaptureArea::ClientArea => {
+            let mut client_rect = RECT::default();
+            GetClientRect(hwnd, &mut client_rect)?;
+
+            let mut top_left = POINT {
+                x: client_rect.left,
+                y: client_rect.top,
+            };
+            let mut bottom_right = POINT {
+                x: client_rect.right,
+                y: client_rect.bottom,
+            };
+
+            if !ClientToScreen(hwnd, &mut top_left).as_bool()
+                || !ClientToScreen(hwnd, &mut bottom_right).as_bool()
+            {
+                return Err(XCapError::new("ClientToScreen failed"));
+            }
+
+            Ok(RECT {
+                left: top_left.x,
+                top: top_left.y,
+                right: bottom_right.x,
+                bottom: bottom_right.y,
+            })
+        }
+    }
+}
+
+// 窗口可以在两次截图之间改变大小，所以 capture_window 和 CaptureSession 每次都要
+// 重新算一遍目标尺寸，而不是只在创建时算一次。DPI 感知只需要在进程里开一次，调用方
+// 负责在这之前自行调用一次 SetProcessDPIAware，这里不会重复调用
+unsafe fn window_capture_size(
+    hwnd: HWND,
+    scale_factor: f32,
+    capture_area: WindowCaptureArea,
+) -> XCapResult<(RECT, i32, i32)> {
+    let rect = window_capture_rect(hwnd, capture_area)?;
+    let mut width = rect.right - rect.left;
+    let mut height = rect.bottom - rect.top;
+
+    if width == 0 {
+        width = GetSystemMetrics(SM_CXSCREEN);
+    }
+    if height == 0 {
+        height = GetSystemMetrics(SM_CYSCREEN);
+    }
+
+    width = (width as f32 * scale_factor) as i32;
+    height = (height as f32 * scale_factor) as i32;
+
+    Ok((rect, width, height))
+}
 
-        let previous_object = SelectObject(*box_hdc_mem, *box_h_bitmap);
+// 创建一块内存 DC，并把一张尺寸匹配的 DIB section 位图选入其中
+unsafe fn create_mem_dc_and_bitmap(
+    hdc_desktop_window: windows::Win32::Graphics::Gdi::HDC,
+    width: i32,
+    height: i32,
+) -> XCapResult<(BoxHDC, BoxHBITMAP, *mut u8)> {
+    // 内存中的HDC，使用 DeleteDC 函数释放
+    // https://learn.microsoft.com/zh-cn/windows/win32/api/wingdi/nf-wingdi-createcompatibledc
+    let box_hdc_mem = BoxHDC::new(CreateCompatibleDC(hdc_desktop_window), None);
+    let (box_h_bitmap, bits) = create_dib_section_bitmap(*box_hdc_mem, width, height)?;
+
+    SelectObject(*box_hdc_mem, *box_h_bitmap);
+
+    Ok((box_hdc_mem, box_h_bitmap, bits))
+}
+
+// capture_window_frame 需要的 GDI 句柄一直是一起传递、一起使用的，打包成一个结构体
+// 避免函数参数堆成一长串，触发 clippy::too_many_arguments
+#[derive(Clone, Copy)]
+struct WindowCaptureHandles<'a> {
+    box_hdc_desktop_window: &'a BoxHDC,
+    box_hdc_mem: &'a BoxHDC,
+    box_h_bitmap: &'a BoxHBITMAP,
+    bits: *const u8,
+}
+
+fn capture_window_frame(
+    hwnd: HWND,
+    handles: &WindowCaptureHandles,
+    rect: RECT,
+    width: i32,
+    height: i32,
+    capture_area: WindowCaptureArea,
+    pixel_format: PixelFormat,
+) -> XCapResult<CapturedImage> {
+    let WindowCaptureHandles {
+        box_hdc_desktop_window,
+        box_hdc_mem,
+        box_h_bitmap,
+        bits,
+    } = *handles;
+
+    unsafe {
+        let previous_object = SelectObject(**box_hdc_mem, **box_h_bitmap);
+
+        // PrintWindow 画的是整个窗口的内容，没法对齐到客户区/扩展边界这类局部矩形，
+        // 所以这条路径只用于完整窗口模式，其余模式直接走下面的 BitBlt
+        let printed_full_window = capture_area == WindowCaptureArea::FullWindow
+            && PrintWindow(hwnd, **box_hdc_mem, PW_RENDERFULLCONTENT).as_bool();
 
-        let mut is_success = false;
+        // GDI 会对 DC 上的绘制调用做批处理，DIB section 的内存不保证在 BitBlt/PrintWindow
+        // 返回时就已经写完；GetDIBits 本身会隐式 flush，但这里绕过了它直接读指针，所以
+        // is_print_window_buffer_empty 探测缓冲区之前也要先强制 flush 一次
+        GdiFlush();
+
+        let mut is_success =
+            printed_full_window && !is_print_window_buffer_empty(bits, width, height);
 
         if !is_success {
             is_success = BitBlt(
-                *box_hdc_mem,
+                **box_hdc_mem,
                 0,
                 0,
                 width,
                 height,
-                *box_hdc_desktop_window,
+                **box_hdc_desktop_window,
                 rect.left,
                 rect.top,
                 SRCCOPY,
             )
             .is_ok();
+
+            GdiFlush();
+        }
+
+        SelectObject(**box_hdc_mem, previous_object);
+
+        if !is_success {
+            return Err(XCapError::new("Capture window failed"));
         }
 
-        SelectObject(*box_hdc_mem, previous_object);
+        dib_section_to_image(bits, width, height, pixel_format)
+    }
+}
+
+#[allow(unused)]
+pub fn capture_window(hwnd: HWND, scale_factor: f32) -> XCapResult<RgbaImage> {
+    capture_window_with_area(
+        hwnd,
+        scale_factor,
+        WindowCaptureArea::FullWindow,
+        PixelFormat::Rgba,
+    )
+    .and_then(CapturedImage::into_rgba)
+}
 
-        to_rgba_image(box_hdc_mem, box_h_bitmap, width, height)
+#[allow(unused)]
+pub fn capture_window_with_area(
+    hwnd: HWND,
+    scale_factor: f32,
+    capture_area: WindowCaptureArea,
+    pixel_format: PixelFormat,
+) -> XCapResult<CapturedImage> {
+    unsafe {
+        SetProcessDPIAware();
+
+        let box_hdc_desktop_window: BoxHDC = BoxHDC::from(GetDesktopWindow());
+        let (rect, width, height) = window_capture_size(hwnd, scale_factor, capture_area)?;
+        let (box_hdc_mem, box_h_bitmap, bits) =
+            create_mem_dc_and_bitmap(*box_hdc_desktop_window, width, height)?;
+
+        capture_window_frame(
+            hwnd,
+            &WindowCaptureHandles {
+                box_hdc_desktop_window: &box_hdc_desktop_window,
+                box_hdc_mem: &box_hdc_mem,
+                box_h_bitmap: &box_h_bitmap,
+                bits,
+            },
+            rect,
+            width,
+            height,
+            capture_area,
+            pixel_format,
+        )
+    }
+}
+
+/// 复用同一显示器区域或同一窗口的 GDI 资源做连续截图的会话，适合预览、录制这类轮询场景。
+/// 只有当目标尺寸发生变化时才会重新创建内存 DC 和位图；显示器会话在 Windows 8 及以上
+/// 优先复用缓存的 DXGI Desktop Duplication 抓帧，拿不到时重跑 BitBlt，窗口会话重跑
+/// PrintWindow/BitBlt，避免 [`capture_monitor`]/[`capture_window`] 每次调用都重新申请句柄的开销。
+pub struct CaptureSession {
+    target: CaptureTarget,
+    pixel_format: PixelFormat,
+    box_hdc_desktop_window: BoxHDC,
+    box_hdc_mem: BoxHDC,
+    box_h_bitmap: BoxHBITMAP,
+    bits: *mut u8,
+    width: i32,
+    height: i32,
+    // 只有 Monitor 会话会用到：复用同一个 IDXGIOutputDuplication 连续抓帧，
+    // 比每帧都走 GDI BitBlt 快得多。为 None 时（Windows 7 及以下，或 DXGI 初始化/
+    // 持续失败）next_frame 退回到上面缓存好的 GDI 资源
+    dxgi: Option<MonitorDxgiSession>,
+}
+
+// 一个 Monitor 会话复用的 DXGI 资源，device/output1 只在创建会话时建一次，
+// duplication 在 DXGI_ERROR_ACCESS_LOST 时才会重建
+struct MonitorDxgiSession {
+    device: ID3D11Device,
+    output1: IDXGIOutput1,
+    duplication: IDXGIOutputDuplication,
+}
+
+enum CaptureTarget {
+    Monitor {
+        x: i32,
+        y: i32,
+    },
+    Window {
+        hwnd: HWND,
+        scale_factor: f32,
+        capture_area: WindowCaptureArea,
+    },
+}
+
+impl CaptureSession {
+    /// 创建一个固定截取 `(x, y, width, height)` 这块屏幕区域的会话
+    pub fn for_monitor(
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        pixel_format: PixelFormat,
+    ) -> XCapResult<Self> {
+        unsafe {
+            SetProcessDPIAware();
+            let box_hdc_desktop_window = BoxHDC::from(GetDesktopWindow());
+            let (box_hdc_mem, box_h_bitmap, bits) =
+                create_mem_dc_and_bitmap(*box_hdc_desktop_window, width, height)?;
+
+            // Desktop Duplication 只在 Windows 8 及以上可用，部分虚拟机/远程桌面环境下
+            // 创建设备或 duplication 也可能失败，这些情况都退回到上面缓存的 GDI 资源，
+            // 和 capture_monitor_with_format 的策略一致
+            let dxgi = if get_os_major_version() >= 8 {
+                create_dxgi_duplication(x, y, width, height)
+                    .ok()
+                    .map(|(device, output1, duplication)| MonitorDxgiSession {
+                        device,
+                        output1,
+                        duplication,
+                    })
+            } else {
+                None
+            };
+
+            Ok(Self {
+                target: CaptureTarget::Monitor { x, y },
+                pixel_format,
+                box_hdc_desktop_window,
+                box_hdc_mem,
+                box_h_bitmap,
+                bits,
+                width,
+                height,
+                dxgi,
+            })
+        }
+    }
+
+    /// 创建一个持续截取 `hwnd` 的会话，窗口尺寸变化时会自动重建位图
+    pub fn for_window(
+        hwnd: HWND,
+        scale_factor: f32,
+        capture_area: WindowCaptureArea,
+        pixel_format: PixelFormat,
+    ) -> XCapResult<Self> {
+        unsafe {
+            SetProcessDPIAware();
+
+            let box_hdc_desktop_window = BoxHDC::from(GetDesktopWindow());
+            let (_rect, width, height) = window_capture_size(hwnd, scale_factor, capture_area)?;
+            let (box_hdc_mem, box_h_bitmap, bits) =
+                create_mem_dc_and_bitmap(*box_hdc_desktop_window, width, height)?;
+
+            Ok(Self {
+                target: CaptureTarget::Window {
+                    hwnd,
+                    scale_factor,
+                    capture_area,
+                },
+                pixel_format,
+                box_hdc_desktop_window,
+                box_hdc_mem,
+                box_h_bitmap,
+                bits,
+                width,
+                height,
+                dxgi: None,
+            })
+        }
+    }
+
+    // 复用 self.dxgi 里缓存的 IDXGIOutputDuplication 抓一帧。返回 None 表示这一帧没能
+    // 通过 DXGI 拿到（会话没有可用的 DXGI 资源，或者这一帧抓取失败），调用方应该退回到
+    // GDI 路径；命中 DXGI_ERROR_ACCESS_LOST 时先尝试重建 duplication 再重试一次，如果
+    // 重建/重试还是失败就清掉 self.dxgi，这个会话之后的帧都走 GDI，不会每帧都重新尝试
+    fn next_dxgi_frame(&mut self) -> Option<Vec<u8>> {
+        let dxgi = self.dxgi.as_mut()?;
+
+        match unsafe { capture_dxgi_frame(&dxgi.device, &dxgi.duplication, self.width, self.height) }
+        {
+            Ok(buffer) => Some(buffer),
+            Err(err) if err.code() == DXGI_ERROR_ACCESS_LOST => {
+                // 桌面切换、锁屏、UAC 弹窗等都会让 duplication 对象失效，重建后再试一次
+                let retried = unsafe { dxgi.output1.DuplicateOutput(&dxgi.device) }
+                    .ok()
+                    .and_then(|duplication| {
+                        dxgi.duplication = duplication;
+                        unsafe {
+                            capture_dxgi_frame(&dxgi.device, &dxgi.duplication, self.width, self.height)
+                        }
+                        .ok()
+                    });
+
+                if retried.is_none() {
+                    self.dxgi = None;
+                }
+
+                retried
+            }
+            // AcquireNextFrame 在屏幕没有变化时会以 DXGI_ERROR_WAIT_TIMEOUT 这类错误超时，
+            // 这只是这一帧没拿到新画面，不代表 duplication 本身失效了，所以这里只退回 GDI
+            // 抓这一帧，不清空 self.dxgi——下一帧还会优先尝试 DXGI
+            Err(_) => None,
+        }
+    }
+
+    /// 拿一帧新的画面。目标尺寸不变时不会重建内存 DC 和位图；显示器会话优先走缓存的
+    /// DXGI duplication（遇到 DXGI_ERROR_ACCESS_LOST 时会重建 duplication），拿不到时
+    /// 重跑 BitBlt，窗口会话重跑 PrintWindow/BitBlt
+    pub fn next_frame(&mut self) -> XCapResult<CapturedImage> {
+        match self.target {
+            CaptureTarget::Monitor { x, y } => {
+                if let Some(buffer) = self.next_dxgi_frame() {
+                    return dxgi_buffer_to_image(buffer, self.width, self.height, self.pixel_format);
+                }
+
+                unsafe {
+                    BitBlt(
+                        *self.box_hdc_mem,
+                        0,
+                        0,
+                        self.width,
+                        self.height,
+                        *self.box_hdc_desktop_window,
+                        x,
+                        y,
+                        SRCCOPY,
+                    )?;
+
+                    GdiFlush();
+
+                    dib_section_to_image(self.bits, self.width, self.height, self.pixel_format)
+                }
+            }
+            CaptureTarget::Window {
+                hwnd,
+                scale_factor,
+                capture_area,
+            } => {
+                let (rect, width, height) =
+                    unsafe { window_capture_size(hwnd, scale_factor, capture_area)? };
+
+                if width != self.width || height != self.height {
+                    let (box_hdc_mem, box_h_bitmap, bits) = unsafe {
+                        create_mem_dc_and_bitmap(*self.box_hdc_desktop_window, width, height)?
+                    };
+
+                    self.box_hdc_mem = box_hdc_mem;
+                    self.box_h_bitmap = box_h_bitmap;
+                    self.bits = bits;
+                    self.width = width;
+                    self.height = height;
+                }
+
+                capture_window_frame(
+                    hwnd,
+                    &WindowCaptureHandles {
+                        box_hdc_desktop_window: &self.box_hdc_desktop_window,
+                        box_hdc_mem: &self.box_hdc_mem,
+                        box_h_bitmap: &self.box_h_bitmap,
+                        bits: self.bits,
+                    },
+                    rect,
+                    self.width,
+                    self.height,
+                    capture_area,
+                    self.pixel_format,
+                )
+            }
+        }
     }
 }